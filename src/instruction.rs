@@ -0,0 +1,146 @@
+use borsh::BorshDeserialize;
+use solana_program::program_error::ProgramError;
+
+pub enum ReviewInstruction {
+    AddReview {
+        title: String,
+        rating: u8,
+        description: String,
+        location: String,
+    },
+    UpdateReview {
+        title: String,
+        rating: u8,
+        description: String,
+    },
+    CloseReview {
+        title: String,
+    },
+    MigrateReview {
+        title: String,
+    },
+    AddComment {
+        comment: String,
+    },
+}
+
+#[derive(BorshDeserialize)]
+struct ReviewPayload {
+    title: String,
+    rating: u8,
+    description: String,
+}
+
+#[derive(BorshDeserialize)]
+struct AddReviewPayload {
+    title: String,
+    rating: u8,
+    description: String,
+    location: String,
+}
+
+#[derive(BorshDeserialize)]
+struct TitlePayload {
+    title: String,
+}
+
+#[derive(BorshDeserialize)]
+struct CommentPayload {
+    comment: String,
+}
+
+impl ReviewInstruction {
+    /// Unpacks a byte buffer into a ReviewInstruction.
+    /// The first byte selects the variant, the remaining bytes are the borsh-serialized payload.
+    pub fn unpack(input: &[u8]) -> Result<Self, ProgramError> {
+        let (&variant, rest) = input
+            .split_first()
+            .ok_or(ProgramError::InvalidInstructionData)?;
+
+        Ok(match variant {
+            0 => {
+                let payload = AddReviewPayload::try_from_slice(rest)
+                    .map_err(|_| ProgramError::InvalidInstructionData)?;
+                Self::AddReview {
+                    title: payload.title,
+                    rating: payload.rating,
+                    description: payload.description,
+                    location: payload.location,
+                }
+            }
+            1 => {
+                let payload = ReviewPayload::try_from_slice(rest)
+                    .map_err(|_| ProgramError::InvalidInstructionData)?;
+                Self::UpdateReview {
+                    title: payload.title,
+                    rating: payload.rating,
+                    description: payload.description,
+                }
+            }
+            2 => {
+                let payload = TitlePayload::try_from_slice(rest)
+                    .map_err(|_| ProgramError::InvalidInstructionData)?;
+                Self::CloseReview {
+                    title: payload.title,
+                }
+            }
+            3 => {
+                let payload = TitlePayload::try_from_slice(rest)
+                    .map_err(|_| ProgramError::InvalidInstructionData)?;
+                Self::MigrateReview {
+                    title: payload.title,
+                }
+            }
+            4 => {
+                let payload = CommentPayload::try_from_slice(rest)
+                    .map_err(|_| ProgramError::InvalidInstructionData)?;
+                Self::AddComment {
+                    comment: payload.comment,
+                }
+            }
+            _ => return Err(ProgramError::InvalidInstructionData),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Borsh encodes a String as a little-endian u32 length prefix followed by
+    // its UTF-8 bytes; build instruction buffers by hand the way a client would.
+    fn encode_string(value: &str) -> Vec<u8> {
+        let mut bytes = (value.len() as u32).to_le_bytes().to_vec();
+        bytes.extend_from_slice(value.as_bytes());
+        bytes
+    }
+
+    #[test]
+    fn unpack_add_comment_reads_the_comment_string() {
+        let mut input = vec![4u8];
+        input.extend(encode_string("looks like it closed early"));
+
+        let instruction = ReviewInstruction::unpack(&input).unwrap();
+
+        match instruction {
+            ReviewInstruction::AddComment { comment } => {
+                assert_eq!(comment, "looks like it closed early");
+            }
+            _ => panic!("expected AddComment"),
+        }
+    }
+
+    #[test]
+    fn unpack_add_comment_rejects_truncated_payload() {
+        let input = vec![4u8, 5, 0, 0, 0, b'h', b'i']; // claims 5 bytes, only provides 2
+
+        assert!(ReviewInstruction::unpack(&input).is_err());
+    }
+
+    #[test]
+    fn unpack_rejects_unknown_variant_tag() {
+        let input = vec![255u8];
+
+        assert!(ReviewInstruction::unpack(&input).is_err());
+    }
+}
@@ -2,16 +2,23 @@ pub mod state;
 pub mod instruction;
 
 use borsh::{BorshDeserialize, BorshSerialize};
+use instruction::ReviewInstruction;
 use solana_program::{
     account_info::{next_account_info, AccountInfo},
     borsh0_10::try_from_slice_unchecked,
     entrypoint,
     entrypoint::ProgramResult,
+    program::{invoke, invoke_signed},
     program_error::ProgramError,
     program_pack::IsInitialized,
     pubkey::Pubkey,
     system_instruction,
-    sysvar::{rent::Rent, Sysvar},
+    sysvar::{clock::Clock, rent::Rent, Sysvar},
+};
+use state::{
+    check_review_discriminator, resize_kind, review_account_len, write_review_discriminator,
+    AccountState, AccountStateV1, ResizeKind, ReviewComment, ReviewCommentCounter, ReviewError,
+    ACCOUNT_STATE_VERSION, MAX_REVIEW_TEXT_LEN, REVIEW_DISCRIMINATOR_LEN,
 };
 
 // Declare and export the program's entrypoint
@@ -23,22 +30,25 @@ pub fn process_instruction(
     accounts: &[AccountInfo], // Array of accounts needed to execute an instruction.
     instruction_data: &[u8], // Serialized data specific to an instruction.
 ) -> ProgramResult {
-    
     // The instruction_data passed into the entrypoint is deserialized to determine its corresponding enum variant.
     let instruction = ReviewInstruction::unpack(instruction_data)?;
     match instruction {
         ReviewInstruction::AddReview {
-            title: String,
-            rating: u8,
-            description: String,
-        } => add_review(program_id, accounts, title, rating, description), // instruction handler
+            title,
+            rating,
+            description,
+            location,
+        } => add_review(program_id, accounts, title, rating, description, location), // instruction handler
         ReviewInstruction::UpdateReview {
-            title: String,
-            rating: u8,
-            description: String,
+            title,
+            rating,
+            description,
         } => update_review(program_id, accounts, title, rating, description), // instruction handler
+        ReviewInstruction::CloseReview { title } => close_review(program_id, accounts, title), // instruction handler
+        ReviewInstruction::MigrateReview { title } => migrate_review(program_id, accounts, title), // instruction handler
+        ReviewInstruction::AddComment { comment } => add_comment(program_id, accounts, comment), // instruction handler
     }
-};
+}
 
 // instruction handler
 // implements the logic required to execute that instruction
@@ -47,17 +57,18 @@ pub fn add_review(
     accounts: &[AccountInfo],
     title: String,
     rating: u8,
-    description: String
+    description: String,
+    location: String
 ) -> ProgramResult {
     let account_info_iter = &mut accounts.iter();
 
-    // The next_account_info function is used to access the next item in the iterator. 
+    // The next_account_info function is used to access the next item in the iterator.
     let initializer = next_account_info(account_info_iter)?;
     let pda_account = next_account_info(account_info_iter)?;
     let system_program = next_account_info(account_info_iter)?;
 
-    if initializer.is_signer {
-        return Err(ProgramError::MissingRequiredSignuture);
+    if !initializer.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
     }
 
     // SEEDS CONTROL
@@ -69,24 +80,28 @@ pub fn add_review(
     }
 
     if rating > 10 || rating < 1 {
-        return Err(ProgramError::InvalidRating.into());
+        return Err(ReviewError::InvalidRating.into());
+    }
+
+    if title.len() + description.len() + location.len() > MAX_REVIEW_TEXT_LEN {
+        return Err(ReviewError::ReviewTooLarge.into());
     }
 
-    let account_len:usize = 1000;
+    let account_len: usize = review_account_len(&title, &description, &location);
 
-    let rent = Rent::get();
+    let rent = Rent::get()?;
     let rent_lamports = rent.minimum_balance(account_len);
 
     invoke_signed(
         &system_instruction::create_account(
-            initializer.key, 
-            pda_account.key, 
-            rent_lamports, 
-            account_len.try_into().unwrap(), 
+            initializer.key,
+            pda_account.key,
+            rent_lamports,
+            account_len.try_into().unwrap(),
             program_id),
     &[
-        initializer.clone(), 
-        pda_account.clone(), 
+        initializer.clone(),
+        pda_account.clone(),
         system_program.clone()],
     &[&[
         initializer.key.as_ref(),
@@ -94,22 +109,29 @@ pub fn add_review(
         &[bump_seed],
     ]])?;
 
-    let mut account_data = trye_from_slice_unchecked::<AccountState>(&pda_account.data.borrow()).unwrap();
+    write_review_discriminator(&mut pda_account.data.borrow_mut());
 
-    if account_data.is_initialized {
+    let mut account_data =
+        try_from_slice_unchecked::<AccountState>(&pda_account.data.borrow()[REVIEW_DISCRIMINATOR_LEN..])
+            .unwrap();
+
+    if account_data.is_initialized() {
         return Err(ProgramError::AccountAlreadyInitialized);
     }
 
+    account_data.version = ACCOUNT_STATE_VERSION;
     account_data.title = title;
     account_data.description = description;
     account_data.rating = rating;
+    account_data.location = location;
+    account_data.timestamp = Clock::get()?.unix_timestamp;
     account_data.is_initialized = true;
 
-    // After the account has been successfully created, the final step is to serialize data into the new account's data fields. 
+    // After the account has been successfully created, the final step is to serialize data into the new account's data fields.
     // This effectively initializes the account data, storing the data passed into the program entrypoint.
-    account_data.serialize(&mut &mut pda_account.data.borrow_mut()[..])?;
+    account_data.serialize(&mut &mut pda_account.data.borrow_mut()[REVIEW_DISCRIMINATOR_LEN..])?;
 
-    Ok(());
+    Ok(())
 }
 
 // instruction handler
@@ -122,25 +144,30 @@ pub fn update_review(
 ) -> ProgramResult {
     let account_info_iter = &mut accounts.iter();
 
-    let initializer = next_account_info(account_info_iter);
-    let pda_account = next_account_info(account_info_iter);
+    let initializer = next_account_info(account_info_iter)?;
+    let pda_account = next_account_info(account_info_iter)?;
+    let system_program = next_account_info(account_info_iter)?;
 
     if pda_account.owner != program_id {
         return Err(ProgramError::IllegalOwner);
     }
 
     if !initializer.is_signer {
-        return Err(ProgramError::MissingRequiredSignuture);
+        return Err(ProgramError::MissingRequiredSignature);
     }
 
-    let mut account_data = try_from_slice_unchecked<AccountState>(&pda_account.data.borrow()).unwrap();
+    check_review_discriminator(&pda_account.data.borrow())?;
+
+    let mut account_data =
+        try_from_slice_unchecked::<AccountState>(&pda_account.data.borrow()[REVIEW_DISCRIMINATOR_LEN..])
+            .unwrap();
 
     // SEEDS CONTROL
     let (pda, _bump_seed) = Pubkey::find_program_address(
         &[
-            initializer.key.as_ref(), 
+            initializer.key.as_ref(),
             account_data.title.as_bytes().as_ref()
-        ], 
+        ],
         program_id,
     );
 
@@ -156,10 +183,363 @@ pub fn update_review(
         return Err(ReviewError::InvalidRating.into());
     }
 
+    if account_data.title.len() + description.len() + account_data.location.len() > MAX_REVIEW_TEXT_LEN {
+        return Err(ReviewError::ReviewTooLarge.into());
+    }
+
     account_data.description = description;
     account_data.rating = rating;
 
-    account_data.serialize(&mut &mut pda_account.data.borrow_mut()[..])?;
+    // The new description may no longer fit the buffer this PDA was created
+    // with; grow or shrink it and keep it rent-exempt rather than relying on
+    // the fixed 1000-byte allocation every review used to get.
+    let new_account_len =
+        review_account_len(&account_data.title, &account_data.description, &account_data.location);
+    let current_account_len = pda_account.data_len();
+
+    match resize_kind(current_account_len, new_account_len) {
+        ResizeKind::Grow => {
+            let rent = Rent::get()?;
+            let new_rent_minimum = rent.minimum_balance(new_account_len);
+            let lamports_needed = new_rent_minimum.saturating_sub(pda_account.lamports());
+            if lamports_needed > 0 {
+                invoke(
+                    &system_instruction::transfer(initializer.key, pda_account.key, lamports_needed),
+                    &[
+                        initializer.clone(),
+                        pda_account.clone(),
+                        system_program.clone(),
+                    ],
+                )?;
+            }
+            pda_account.realloc(new_account_len, false)?;
+        }
+        ResizeKind::Shrink => {
+            let rent = Rent::get()?;
+            let new_rent_minimum = rent.minimum_balance(new_account_len);
+            pda_account.realloc(new_account_len, false)?;
+            let lamports_excess = pda_account.lamports().saturating_sub(new_rent_minimum);
+            if lamports_excess > 0 {
+                **pda_account.lamports.borrow_mut() -= lamports_excess;
+                **initializer.lamports.borrow_mut() += lamports_excess;
+            }
+        }
+        ResizeKind::Unchanged => {}
+    }
+
+    account_data.serialize(&mut &mut pda_account.data.borrow_mut()[REVIEW_DISCRIMINATOR_LEN..])?;
+
+    Ok(())
+}
+
+// instruction handler
+// reclaims the rent locked in a review PDA by zeroing its data and returning
+// its lamports to the initializer, mirroring the SPL Record program's delete flow
+pub fn close_review(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    title: String,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let initializer = next_account_info(account_info_iter)?;
+    let pda_account = next_account_info(account_info_iter)?;
+
+    if pda_account.owner != program_id {
+        return Err(ProgramError::IllegalOwner);
+    }
+
+    if !initializer.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    // SEEDS CONTROL
+    let (pda, _bump_seed) = Pubkey::find_program_address(
+        &[initializer.key.as_ref(), title.as_bytes().as_ref()],
+        program_id,
+    );
+
+    if pda != *pda_account.key {
+        return Err(ReviewError::InvalidPDA.into());
+    }
+
+    check_review_discriminator(&pda_account.data.borrow())?;
+
+    let mut account_data =
+        try_from_slice_unchecked::<AccountState>(&pda_account.data.borrow()[REVIEW_DISCRIMINATOR_LEN..])
+            .unwrap();
+
+    if !account_data.is_initialized() {
+        return Err(ReviewError::UninitializedAccount.into());
+    }
+
+    // Clear the stored review so the zeroed buffer can't be force-deserialized
+    // back into a live AccountState.
+    account_data.is_initialized = false;
+    for byte in pda_account.data.borrow_mut().iter_mut() {
+        *byte = 0;
+    }
+
+    let dest_starting_lamports = initializer.lamports();
+    **initializer.lamports.borrow_mut() = dest_starting_lamports
+        .checked_add(pda_account.lamports())
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+    **pda_account.lamports.borrow_mut() = 0;
+
+    Ok(())
+}
+
+// instruction handler
+// upgrades a review PDA still on AccountStateV1 to the current AccountStateV2
+// layout; a no-op if the account is already at ACCOUNT_STATE_VERSION
+pub fn migrate_review(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    title: String,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let initializer = next_account_info(account_info_iter)?;
+    let pda_account = next_account_info(account_info_iter)?;
+    let system_program = next_account_info(account_info_iter)?;
+
+    if pda_account.owner != program_id {
+        return Err(ProgramError::IllegalOwner);
+    }
+
+    if !initializer.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    // SEEDS CONTROL
+    let (pda, _bump_seed) = Pubkey::find_program_address(
+        &[initializer.key.as_ref(), title.as_bytes().as_ref()],
+        program_id,
+    );
+
+    if pda != *pda_account.key {
+        return Err(ReviewError::InvalidPDA.into());
+    }
+
+    // Accounts created before chunk0-3 were never stamped with
+    // REVIEW_DISCRIMINATOR at all, so gating on it here (as every other
+    // handler does) would make the legacy AccountStateV1 branch below
+    // unreachable -- exactly the accounts this instruction exists to fix.
+    // Check for the discriminator first and only use it to decide whether
+    // the account is already-migrated, rather than bailing out on it.
+    let has_discriminator = check_review_discriminator(&pda_account.data.borrow()).is_ok();
+
+    if has_discriminator {
+        // The leading byte after the discriminator is the version tag in
+        // AccountStateV2; migrating twice should be a no-op rather than
+        // reinterpreting already-migrated data.
+        let version = *pda_account
+            .data
+            .borrow()
+            .get(REVIEW_DISCRIMINATOR_LEN)
+            .ok_or(ReviewError::UninitializedAccount)?;
+
+        if version == ACCOUNT_STATE_VERSION {
+            return Ok(());
+        }
+    }
+
+    let old_data = if has_discriminator {
+        try_from_slice_unchecked::<AccountStateV1>(&pda_account.data.borrow()[REVIEW_DISCRIMINATOR_LEN..])
+            .unwrap()
+    } else {
+        try_from_slice_unchecked::<AccountStateV1>(&pda_account.data.borrow()).unwrap()
+    };
+
+    if !old_data.is_initialized() {
+        return Err(ReviewError::UninitializedAccount.into());
+    }
+
+    let migrated = AccountState {
+        version: ACCOUNT_STATE_VERSION,
+        is_initialized: old_data.is_initialized,
+        rating: old_data.rating,
+        description: old_data.description,
+        title: old_data.title,
+        location: String::new(),
+        timestamp: 0,
+    };
+
+    // A legacy V1 buffer was sized by the old hardcoded 1000-byte allocation,
+    // so the new V2 layout (discriminator + a short title/description) is
+    // often *smaller*, not bigger -- resize both ways and refund the freed
+    // rent, the same way update_review does on a description change.
+    let new_len = review_account_len(&migrated.title, &migrated.description, &migrated.location);
+    let current_len = pda_account.data_len();
+
+    match resize_kind(current_len, new_len) {
+        ResizeKind::Grow => {
+            let rent = Rent::get()?;
+            let new_rent_minimum = rent.minimum_balance(new_len);
+            let lamports_needed = new_rent_minimum.saturating_sub(pda_account.lamports());
+            if lamports_needed > 0 {
+                invoke(
+                    &system_instruction::transfer(initializer.key, pda_account.key, lamports_needed),
+                    &[
+                        initializer.clone(),
+                        pda_account.clone(),
+                        system_program.clone(),
+                    ],
+                )?;
+            }
+            pda_account.realloc(new_len, false)?;
+        }
+        ResizeKind::Shrink => {
+            let rent = Rent::get()?;
+            let new_rent_minimum = rent.minimum_balance(new_len);
+            pda_account.realloc(new_len, false)?;
+            let lamports_excess = pda_account.lamports().saturating_sub(new_rent_minimum);
+            if lamports_excess > 0 {
+                **pda_account.lamports.borrow_mut() -= lamports_excess;
+                **initializer.lamports.borrow_mut() += lamports_excess;
+            }
+        }
+        ResizeKind::Unchanged => {}
+    }
+
+    write_review_discriminator(&mut pda_account.data.borrow_mut());
+    migrated.serialize(&mut &mut pda_account.data.borrow_mut()[REVIEW_DISCRIMINATOR_LEN..])?;
 
     Ok(())
-}
\ No newline at end of file
+}
+
+// instruction handler
+// posts a reply to a review, lazily creating the review's comment counter the
+// first time it's commented on and using the counter to seed each new comment PDA
+pub fn add_comment(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    comment: String,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let commenter = next_account_info(account_info_iter)?;
+    let review_pda_account = next_account_info(account_info_iter)?;
+    let counter_pda_account = next_account_info(account_info_iter)?;
+    let comment_pda_account = next_account_info(account_info_iter)?;
+    let system_program = next_account_info(account_info_iter)?;
+
+    if !commenter.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if review_pda_account.owner != program_id {
+        return Err(ProgramError::IllegalOwner);
+    }
+
+    // Make sure review_pda_account is actually a live Review and not some
+    // other program-owned account (another review's counter, a closed
+    // review, etc) being passed in as raw seed material.
+    check_review_discriminator(&review_pda_account.data.borrow())?;
+
+    let review_data = try_from_slice_unchecked::<AccountState>(
+        &review_pda_account.data.borrow()[REVIEW_DISCRIMINATOR_LEN..],
+    )
+    .unwrap();
+
+    if !review_data.is_initialized() {
+        return Err(ReviewError::UninitializedAccount.into());
+    }
+
+    // SEEDS CONTROL
+    let (counter_pda, counter_bump_seed) = Pubkey::find_program_address(
+        &[review_pda_account.key.as_ref(), "comment".as_bytes()],
+        program_id,
+    );
+
+    if counter_pda != *counter_pda_account.key {
+        return Err(ReviewError::InvalidPDA.into());
+    }
+
+    if counter_pda_account.data_is_empty() {
+        let counter_len: usize = 1 + 8; // is_initialized + counter
+        let rent = Rent::get()?;
+        let rent_lamports = rent.minimum_balance(counter_len);
+
+        invoke_signed(
+            &system_instruction::create_account(
+                commenter.key,
+                counter_pda_account.key,
+                rent_lamports,
+                counter_len.try_into().unwrap(),
+                program_id,
+            ),
+            &[
+                commenter.clone(),
+                counter_pda_account.clone(),
+                system_program.clone(),
+            ],
+            &[&[
+                review_pda_account.key.as_ref(),
+                "comment".as_bytes(),
+                &[counter_bump_seed],
+            ]],
+        )?;
+
+        let counter_data = ReviewCommentCounter {
+            is_initialized: true,
+            counter: 0,
+        };
+        counter_data.serialize(&mut &mut counter_pda_account.data.borrow_mut()[..])?;
+    }
+
+    let mut counter_data =
+        try_from_slice_unchecked::<ReviewCommentCounter>(&counter_pda_account.data.borrow())
+            .unwrap();
+
+    // SEEDS CONTROL
+    let (comment_pda, comment_bump_seed) = Pubkey::find_program_address(
+        &[
+            review_pda_account.key.as_ref(),
+            counter_data.counter.to_be_bytes().as_ref(),
+        ],
+        program_id,
+    );
+
+    if comment_pda != *comment_pda_account.key {
+        return Err(ReviewError::InvalidPDA.into());
+    }
+
+    let account_len: usize = 1 + 32 + 32 + (4 + comment.len()) + 8;
+    let rent = Rent::get()?;
+    let rent_lamports = rent.minimum_balance(account_len);
+
+    invoke_signed(
+        &system_instruction::create_account(
+            commenter.key,
+            comment_pda_account.key,
+            rent_lamports,
+            account_len.try_into().unwrap(),
+            program_id,
+        ),
+        &[
+            commenter.clone(),
+            comment_pda_account.clone(),
+            system_program.clone(),
+        ],
+        &[&[
+            review_pda_account.key.as_ref(),
+            counter_data.counter.to_be_bytes().as_ref(),
+            &[comment_bump_seed],
+        ]],
+    )?;
+
+    let comment_data = ReviewComment {
+        is_initialized: true,
+        review: *review_pda_account.key,
+        commenter: *commenter.key,
+        comment,
+        count: counter_data.counter,
+    };
+    comment_data.serialize(&mut &mut comment_pda_account.data.borrow_mut()[..])?;
+
+    counter_data.counter += 1;
+    counter_data.serialize(&mut &mut counter_pda_account.data.borrow_mut()[..])?;
+
+    Ok(())
+}
@@ -1,21 +1,148 @@
 use borsh::{BorshDeserialize, BorshSerialize};
 use solana_program::program_error::ProgramError;
 use solana_program::program_pack::{IsInitialized, Sealed};
+use solana_program::pubkey::Pubkey;
 use thiserror::Error;
 
 /* We are going to hold this account state inside the PDA */
 
+/// The original on-chain layout. Kept around so `migrate_review` can still
+/// read accounts created before the `location`/`timestamp` fields existed.
 #[derive(BorshSerialize, BorshDeserialize)]
-pub struct AccountState {
+pub struct AccountStateV1 {
     pub is_initialized: bool,
     pub rating: u8,
     pub description: String,
     pub title: String,
 }
 
-impl Sealed for AccountState {}
+impl Sealed for AccountStateV1 {}
 
-impl IsInitialized for AccountState {
+impl IsInitialized for AccountStateV1 {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+/// Bump this whenever the layout of `AccountStateV2` changes, and teach
+/// `migrate_review` how to upgrade accounts written at the previous version.
+pub const ACCOUNT_STATE_VERSION: u8 = 2;
+
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct AccountStateV2 {
+    pub version: u8,
+    pub is_initialized: bool,
+    pub rating: u8,
+    pub description: String,
+    pub title: String,
+    pub location: String,
+    pub timestamp: i64,
+}
+
+impl Sealed for AccountStateV2 {}
+
+impl IsInitialized for AccountStateV2 {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+/// Current on-chain layout. New instructions always read and write this version.
+pub type AccountState = AccountStateV2;
+
+/// Number of bytes reserved at the front of every `Review` account for
+/// `REVIEW_DISCRIMINATOR`.
+pub const REVIEW_DISCRIMINATOR_LEN: usize = 8;
+
+/// First 8 bytes of sha256("account:Review"), prepended to every `Review` PDA so
+/// that an account owned by this program can't be force-deserialized as the
+/// wrong type once a second account type exists.
+pub const REVIEW_DISCRIMINATOR: [u8; 8] = [0x7c, 0x3f, 0xcb, 0xd7, 0xe2, 0x1e, 0xde, 0x0f];
+
+/// Writes `REVIEW_DISCRIMINATOR` into the first 8 bytes of a freshly created `Review` account.
+pub fn write_review_discriminator(data: &mut [u8]) {
+    data[..REVIEW_DISCRIMINATOR_LEN].copy_from_slice(&REVIEW_DISCRIMINATOR);
+}
+
+/// Checks that `data` begins with `REVIEW_DISCRIMINATOR` before it's trusted as a `Review`.
+pub fn check_review_discriminator(data: &[u8]) -> Result<(), ReviewError> {
+    if data.len() < REVIEW_DISCRIMINATOR_LEN || data[..REVIEW_DISCRIMINATOR_LEN] != REVIEW_DISCRIMINATOR {
+        return Err(ReviewError::InvalidAccountDiscriminator);
+    }
+    Ok(())
+}
+
+/// Cap on the combined size of a review's variable-length fields, so a single
+/// PDA can't be grown into an unreasonably expensive account.
+pub const MAX_REVIEW_TEXT_LEN: usize = 2000;
+
+/// Computes the exact number of bytes a `Review` account needs for the given
+/// field contents: the discriminator, the fixed-size fields, and the
+/// borsh length-prefixed strings. Replaces the old hardcoded 1000-byte
+/// allocation so storage cost tracks review length instead of wasting (or
+/// running out of) rent on every account.
+pub fn review_account_len(title: &str, description: &str, location: &str) -> usize {
+    REVIEW_DISCRIMINATOR_LEN
+        + 1 // version
+        + 1 // is_initialized
+        + 1 // rating
+        + (4 + title.len())
+        + (4 + description.len())
+        + (4 + location.len())
+        + 8 // timestamp
+}
+
+/// Which way (if any) an account needs to be resized to go from
+/// `current_len` to `new_len`. Kept separate from the lamport/realloc calls
+/// in `update_review`/`migrate_review` so the branch selection itself can be
+/// unit tested without a runtime `AccountInfo`.
+#[derive(Debug, PartialEq, Eq)]
+pub enum ResizeKind {
+    Grow,
+    Shrink,
+    Unchanged,
+}
+
+pub fn resize_kind(current_len: usize, new_len: usize) -> ResizeKind {
+    if new_len > current_len {
+        ResizeKind::Grow
+    } else if new_len < current_len {
+        ResizeKind::Shrink
+    } else {
+        ResizeKind::Unchanged
+    }
+}
+
+/// Tracks how many comments a review has, so each new `ReviewComment` PDA can
+/// be seeded with a fresh, never-reused count.
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct ReviewCommentCounter {
+    pub is_initialized: bool,
+    pub counter: u64,
+}
+
+impl Sealed for ReviewCommentCounter {}
+
+impl IsInitialized for ReviewCommentCounter {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+/// A single reply to a review, stored at its own PDA seeded by
+/// `[review_pda.key, count.to_be_bytes()]`.
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct ReviewComment {
+    pub is_initialized: bool,
+    pub review: Pubkey,
+    pub commenter: Pubkey,
+    pub comment: String,
+    pub count: u64,
+}
+
+impl Sealed for ReviewComment {}
+
+impl IsInitialized for ReviewComment {
     fn is_initialized(&self) -> bool {
         self.is_initialized
     }
@@ -31,10 +158,87 @@ pub enum ReviewError {
 
     #[error("PDA Error")]
     InvalidPDA,
+
+    #[error("Account discriminator does not match the expected Review tag")]
+    InvalidAccountDiscriminator,
+
+    #[error("Review title/description/location exceed the maximum account size")]
+    ReviewTooLarge,
 }
 
 impl From<ReviewError> for ProgramError {
     fn from(e: ReviewError) -> Self {
         ProgramError::Custom(e as u32)
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn review_account_len_accounts_for_fixed_fields_and_discriminator() {
+        // 8 (discriminator) + 1 (version) + 1 (is_initialized) + 1 (rating)
+        // + 8 (timestamp) + three empty length-prefixed strings (4 bytes each).
+        assert_eq!(review_account_len("", "", ""), 8 + 1 + 1 + 1 + 8 + 4 * 3);
+    }
+
+    #[test]
+    fn review_account_len_grows_with_string_contents() {
+        let empty = review_account_len("", "", "");
+        let with_title = review_account_len("abc", "", "");
+        let with_all = review_account_len("abc", "defgh", "ij");
+
+        assert_eq!(with_title, empty + 3);
+        assert_eq!(with_all, empty + 3 + 5 + 2);
+    }
+
+    #[test]
+    fn resize_kind_detects_growth() {
+        assert_eq!(resize_kind(100, 150), ResizeKind::Grow);
+    }
+
+    #[test]
+    fn resize_kind_detects_shrink() {
+        assert_eq!(resize_kind(150, 100), ResizeKind::Shrink);
+    }
+
+    #[test]
+    fn resize_kind_detects_unchanged() {
+        assert_eq!(resize_kind(100, 100), ResizeKind::Unchanged);
+    }
+
+    #[test]
+    fn review_comment_counter_round_trips_through_borsh() {
+        let counter = ReviewCommentCounter {
+            is_initialized: true,
+            counter: 7,
+        };
+
+        let bytes = counter.try_to_vec().unwrap();
+        let decoded = ReviewCommentCounter::try_from_slice(&bytes).unwrap();
+
+        assert_eq!(decoded.is_initialized, counter.is_initialized);
+        assert_eq!(decoded.counter, counter.counter);
+    }
+
+    #[test]
+    fn review_comment_round_trips_through_borsh() {
+        let comment = ReviewComment {
+            is_initialized: true,
+            review: Pubkey::new_unique(),
+            commenter: Pubkey::new_unique(),
+            comment: "great spot, noisy on weekends".to_string(),
+            count: 3,
+        };
+
+        let bytes = comment.try_to_vec().unwrap();
+        let decoded = ReviewComment::try_from_slice(&bytes).unwrap();
+
+        assert_eq!(decoded.is_initialized, comment.is_initialized);
+        assert_eq!(decoded.review, comment.review);
+        assert_eq!(decoded.commenter, comment.commenter);
+        assert_eq!(decoded.comment, comment.comment);
+        assert_eq!(decoded.count, comment.count);
+    }
 }
\ No newline at end of file